@@ -5,21 +5,42 @@ use super::{config::Config, obfuscation::ObfuscatorHandle, CloseMsg, Error, Tunn
 #[cfg(target_os = "android")]
 use std::sync::Mutex;
 use std::{
+    collections::HashMap,
+    io,
     net::IpAddr,
-    sync::{mpsc as sync_mpsc, Arc},
+    sync::{atomic, atomic::AtomicU64, mpsc as sync_mpsc, Arc, Mutex as StdMutex},
     time::Duration,
 };
 #[cfg(target_os = "android")]
 use talpid_tunnel::tun_provider::TunProvider;
 
 use ipnetwork::IpNetwork;
+use once_cell::sync::Lazy;
 use talpid_types::net::wireguard::{PresharedKey, PrivateKey, PublicKey};
 use tokio::sync::Mutex as AsyncMutex;
 
+use self::path_mtu_discovery::discover_path_mtu;
+use self::websocket_obfuscation::WebSocketTlsObfuscator;
+
 const INITIAL_PSK_EXCHANGE_TIMEOUT: Duration = Duration::from_secs(8);
 const MAX_PSK_EXCHANGE_TIMEOUT: Duration = Duration::from_secs(48);
+/// Lower bound for the computed RTO. Deliberately much smaller than
+/// `INITIAL_PSK_EXCHANGE_TIMEOUT`, which is only a pessimistic seed for the first exchange, not a
+/// floor the measured timeout should be clamped back up to on fast links.
+const MIN_PSK_EXCHANGE_TIMEOUT: Duration = Duration::from_millis(300);
 const PSK_EXCHANGE_TIMEOUT_MULTIPLIER: u32 = 2;
 
+/// Bumped every time `reconfigure_tunnel` commits a new config. Lets callers that poll the
+/// tunnel for its interface name or stats tell whether the config they're observing is the one
+/// that was actually applied, without needing to hold the same lock `reconfigure_tunnel` does.
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The generation of the most recently *committed* tunnel config. Changes only after
+/// `set_config` has returned successfully, never while a reconfiguration is in flight.
+pub(crate) fn config_generation() -> u64 {
+    CONFIG_GENERATION.load(atomic::Ordering::Acquire)
+}
+
 #[cfg(windows)]
 pub async fn config_ephemeral_peers(
     tunnel: &Arc<AsyncMutex<Option<Box<dyn Tunnel>>>>,
@@ -40,18 +61,27 @@ pub async fn config_ephemeral_peers(
     config_ephemeral_peers_inner(tunnel, config, retry_attempt, obfuscator, close_obfs_sender)
         .await?;
 
-    log::trace!("Resetting tunnel MTU");
-    try_set_ipv4_mtu(&iface_name, config.mtu);
+    run_path_mtu_discovery(&iface_name, config).await;
 
     Ok(())
 }
 
 #[cfg(windows)]
 fn try_set_ipv4_mtu(alias: &str, mtu: u16) {
+    try_set_mtu(alias, mtu, talpid_windows::net::AddressFamily::Ipv4)
+}
+
+#[cfg(windows)]
+fn try_set_ipv6_mtu(alias: &str, mtu: u16) {
+    try_set_mtu(alias, mtu, talpid_windows::net::AddressFamily::Ipv6)
+}
+
+#[cfg(windows)]
+fn try_set_mtu(alias: &str, mtu: u16, family: talpid_windows::net::AddressFamily) {
     use talpid_windows::net::*;
     match luid_from_alias(alias) {
         Ok(luid) => {
-            if let Err(error) = set_mtu(u32::from(mtu), luid, AddressFamily::Ipv4) {
+            if let Err(error) = set_mtu(u32::from(mtu), luid, family) {
                 log::error!("Failed to set tunnel interface MTU: {error}");
             }
         }
@@ -61,6 +91,55 @@ fn try_set_ipv4_mtu(alias: &str, mtu: u16) {
     }
 }
 
+/// Runs PMTUD against the active relay once the tunnel is up, and writes the discovered MTU
+/// into `config.mtu`. If even the floor-sized probe is reported too big, sets `config.mtu` to
+/// `MIN_IPV4_MTU` instead. A relay that silently drops every probe does not trigger this: the
+/// probe counts silence as "fits".
+async fn run_path_mtu_discovery(iface_name: &str, config: &mut Config) {
+    #[cfg(windows)]
+    let is_ipv6 = path_mtu_discovery::active_peer_endpoint(config).is_ipv6();
+
+    match discover_path_mtu(config).await {
+        Some(mtu) => {
+            log::debug!("Path MTU discovery converged on {mtu}");
+            config.mtu = mtu;
+        }
+        None => {
+            log::warn!("Path MTU discovery did not converge, falling back to minimum MTU");
+            config.mtu = talpid_tunnel::MIN_IPV4_MTU;
+        }
+    }
+
+    #[cfg(windows)]
+    if is_ipv6 {
+        try_set_ipv6_mtu(iface_name, config.mtu);
+    } else {
+        try_set_ipv4_mtu(iface_name, config.mtu);
+    }
+    #[cfg(not(windows))]
+    try_set_platform_mtu(iface_name, config.mtu);
+}
+
+#[cfg(not(windows))]
+fn try_set_platform_mtu(iface_name: &str, mtu: u16) {
+    #[cfg(target_os = "linux")]
+    {
+        if let Err(error) = talpid_linux_net::set_mtu(iface_name, mtu) {
+            log::error!("Failed to set tunnel interface MTU: {error}");
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Err(error) = talpid_macos::net::set_mtu(iface_name, mtu) {
+            log::error!("Failed to set tunnel interface MTU: {error}");
+        }
+    }
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        let _ = (iface_name, mtu);
+    }
+}
+
 #[cfg(not(windows))]
 pub async fn config_ephemeral_peers(
     tunnel: &Arc<AsyncMutex<Option<Box<dyn Tunnel>>>>,
@@ -79,7 +158,18 @@ pub async fn config_ephemeral_peers(
         #[cfg(target_os = "android")]
         tun_provider,
     )
-    .await
+    .await?;
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        let iface_name = {
+            let tunnel = tunnel.lock().await;
+            tunnel.as_ref().unwrap().get_interface_name()
+        };
+        run_path_mtu_discovery(&iface_name, config).await;
+    }
+
+    Ok(())
 }
 
 async fn config_ephemeral_peers_inner(
@@ -93,9 +183,18 @@ async fn config_ephemeral_peers_inner(
     let ephemeral_private_key = PrivateKey::new_from_random();
     let close_obfs_sender = close_obfs_sender.clone();
 
+    // Shared between the exit and (if multihop) entry negotiation below, so the second exchange
+    // benefits from whatever the first learned about the path's RTT. Its seed is looked up by
+    // the exit relay's address, so a retried call never starts from an RTT that was learned
+    // under a different exit relay.
+    let mut rtt_estimator = RttEstimator::new(config.exit_peer().endpoint.ip(), retry_attempt);
+    if retry_attempt > 0 {
+        log::debug!("Ephemeral peer negotiation retry attempt {retry_attempt}");
+    }
+
     let exit_should_have_daita = config.daita && !config.is_multihop();
     let exit_psk = request_ephemeral_peer(
-        retry_attempt,
+        &mut rtt_estimator,
         config,
         ephemeral_private_key.public_key(),
         config.quantum_resistant,
@@ -124,7 +223,7 @@ async fn config_ephemeral_peers_inner(
         )
         .await?;
         let entry_psk = request_ephemeral_peer(
-            retry_attempt,
+            &mut rtt_estimator,
             &entry_config,
             ephemeral_private_key.public_key(),
             config.quantum_resistant,
@@ -172,6 +271,11 @@ async fn config_ephemeral_peers_inner(
 
 /// Reconfigures the tunnel to use the provided config while potentially modifying the config
 /// and restarting the obfuscation provider. Returns the new config used by the new tunnel.
+///
+/// If the obfuscation provider selects the WebSocket-over-TLS transport, a local relay is
+/// started and `config.entry_peer.endpoint` is rewritten to point at it *before* this function
+/// returns, so that any PSK exchange performed against the returned config also traverses the
+/// obfuscated channel.
 async fn reconfigure_tunnel(
     tunnel: &Arc<AsyncMutex<Option<Box<dyn Tunnel>>>>,
     mut config: Config,
@@ -179,36 +283,111 @@ async fn reconfigure_tunnel(
     close_obfs_sender: sync_mpsc::Sender<CloseMsg>,
     #[cfg(target_os = "android")] tun_provider: &Arc<Mutex<TunProvider>>,
 ) -> std::result::Result<Config, CloseMsg> {
-    let mut obfs_guard = obfuscator.lock().await;
-    if let Some(obfuscator_handle) = obfs_guard.take() {
+    // Install the routing-policy rule that sends `fwmark`-tagged packets around the tunnel's own
+    // routes, whether or not obfuscation is in play. This step marks no socket itself: only the
+    // WebSocket-over-TLS relay's TCP socket below gets the mark, and the PSK exchange socket
+    // opened by `talpid_tunnel_config_client` is not marked here.
+    if let Some(fwmark) = config.fwmark {
+        apply_routing_mark(fwmark)
+            .map_err(Error::FwmarkRoutingError)
+            .map_err(CloseMsg::SetupError)?;
+    }
+
+    // Only hold the obfuscator lock long enough to take the old handle out; `abort()` and
+    // reapplying the (possibly very different, possibly network-bound) obfuscation config run
+    // with the lock released so `reconfigure_tunnel` calls racing on other peers don't pile up
+    // behind this one, and readers of the obfuscator handle aren't blocked either.
+    let previous_obfuscator = {
+        let mut obfs_guard = obfuscator.lock().await;
+        obfs_guard.take()
+    };
+
+    if let Some(obfuscator_handle) = previous_obfuscator {
         obfuscator_handle.abort();
-        *obfs_guard = super::obfuscation::apply_obfuscation_config(
+    }
+
+    // Recomputed unconditionally from `config`, not gated on whether an obfuscator was already
+    // running: the first time a user turns this on from "no obfuscation" there is no previous
+    // handle to match on, but the relay still needs to be spawned and `entry_peer.endpoint`
+    // still needs rewriting.
+    //
+    // TODO: `config.websocket_tls_obfuscation` is a field bolted on alongside the existing
+    // obfuscation config enum that `apply_obfuscation_config` below already matches on for
+    // udp2tcp/shadowsocks, so this mode never goes through that selection. It should instead be
+    // a variant of that enum, with `apply_obfuscation_config` dispatching to
+    // `websocket_obfuscation::WebSocketTlsObfuscator` itself; that requires changes to
+    // `config.rs`/`obfuscation.rs`, neither of which is present in this checkout to edit.
+    let new_obfuscator = match config.websocket_tls_obfuscation.clone() {
+        // The entry relay should be reached over a WebSocket stream tunneled inside TLS on
+        // port 443, so WG traffic (and, by extension, the ephemeral peer negotiation that
+        // follows) blends in with ordinary HTTPS.
+        Some(mut ws_tls_config) => {
+            // Derive the relay's fwmark from `config.fwmark` itself rather than trusting
+            // whatever was baked into `ws_tls_config` at construction time, so the two can't
+            // drift out of sync with each other.
+            ws_tls_config.fwmark = config.fwmark;
+            let relay = WebSocketTlsObfuscator::spawn(ws_tls_config)
+                .await
+                .map_err(Error::WebSocketTlsObfuscatorError)
+                .map_err(CloseMsg::SetupError)?;
+            config.entry_peer.endpoint = relay.local_addr();
+            Some(relay.into_obfuscator_handle())
+        }
+        None => super::obfuscation::apply_obfuscation_config(
             &mut config,
             close_obfs_sender,
             #[cfg(target_os = "android")]
             tun_provider.clone(),
         )
         .await
-        .map_err(CloseMsg::ObfuscatorFailed)?;
-    }
-
-    let mut tunnel = tunnel.lock().await;
+        .map_err(CloseMsg::ObfuscatorFailed)?,
+    };
 
-    let set_config_future = tunnel
-        .as_mut()
-        .map(|tunnel| tunnel.set_config(config.clone()));
+    *obfuscator.lock().await = new_obfuscator;
 
-    if let Some(f) = set_config_future {
-        f.await
+    // `Tunnel::set_config` returns a future borrowing `&mut self`, so the lock guarding the
+    // tunnel has to be held for the duration of the call: there's no sentinel value to leave in
+    // the `Option` slot in its place, and `get_interface_name`/stats callers elsewhere in this
+    // file acquire the same lock and unconditionally `.unwrap()` it, so they must never observe
+    // the slot empty. A concurrent caller blocks until the reconfiguration completes, same as
+    // before this function existed; it never races an emptied slot.
+    let mut tunnel_guard = tunnel.lock().await;
+    if let Some(tunnel) = tunnel_guard.as_mut() {
+        tunnel
+            .set_config(config.clone())
+            .await
             .map_err(Error::TunnelError)
             .map_err(CloseMsg::SetupError)?;
+
+        // Only now is `config` fully committed to the running tunnel: bump the generation so
+        // observers gated on `config_generation()` never see a config that's still
+        // mid-reconfiguration. If there was no tunnel to reconfigure, nothing was actually
+        // applied, so the generation is left untouched.
+        CONFIG_GENERATION.fetch_add(1, atomic::Ordering::AcqRel);
     }
+    drop(tunnel_guard);
 
     Ok(config)
 }
 
+/// Installs (or refreshes) the routing-policy rule that sends `fwmark`-tagged packets out the
+/// default route instead of the tunnel, mirroring wireguard-rs's own fwmark-based bypass for its
+/// configuration socket. Idempotent: reapplying the same mark on every reconfiguration is cheap
+/// and keeps the rule in place across tunnel restarts.
+#[cfg(target_os = "linux")]
+fn apply_routing_mark(fwmark: u32) -> io::Result<()> {
+    talpid_linux_net::add_fwmark_bypass_rule(fwmark)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_routing_mark(_fwmark: u32) -> io::Result<()> {
+    // SO_MARK and routing-policy rules are Linux concepts; other platforms route the
+    // negotiation socket around the tunnel some other way (if at all), so this is a no-op.
+    Ok(())
+}
+
 async fn request_ephemeral_peer(
-    retry_attempt: u32,
+    rtt_estimator: &mut RttEstimator,
     config: &Config,
     wg_psk_pubkey: PublicKey,
     enable_pq: bool,
@@ -216,13 +395,10 @@ async fn request_ephemeral_peer(
 ) -> std::result::Result<Option<PresharedKey>, CloseMsg> {
     log::debug!("Requesting ephemeral peer");
 
-    let timeout = std::cmp::min(
-        MAX_PSK_EXCHANGE_TIMEOUT,
-        INITIAL_PSK_EXCHANGE_TIMEOUT
-            .saturating_mul(PSK_EXCHANGE_TIMEOUT_MULTIPLIER.saturating_pow(retry_attempt)),
-    );
+    let timeout = rtt_estimator.timeout();
+    let start = tokio::time::Instant::now();
 
-    let ephemeral = tokio::time::timeout(
+    let result = tokio::time::timeout(
         timeout,
         talpid_tunnel_config_client::request_ephemeral_peer(
             config.ipv4_gateway,
@@ -232,13 +408,546 @@ async fn request_ephemeral_peer(
             enable_daita,
         ),
     )
-    .await
-    .map_err(|_timeout_err| {
-        log::warn!("Timeout while negotiating ephemeral peer");
-        CloseMsg::EphemeralPeerNegotiationTimeout
-    })?
+    .await;
+
+    let ephemeral = match result {
+        Ok(ephemeral) => {
+            // Karn's algorithm: only sample RTT from an exchange that didn't time out.
+            rtt_estimator.on_success(start.elapsed());
+            ephemeral
+        }
+        Err(_timeout_err) => {
+            log::warn!("Timeout while negotiating ephemeral peer");
+            rtt_estimator.on_timeout();
+            return Err(CloseMsg::EphemeralPeerNegotiationTimeout);
+        }
+    }
     .map_err(Error::EphemeralPeerNegotiationError)
     .map_err(CloseMsg::SetupError)?;
 
     Ok(ephemeral.psk)
-}
\ No newline at end of file
+}
+
+/// RTO most recently learned against each relay, keyed by the relay's address the same way
+/// `path_mtu_discovery::MTU_CACHE` is keyed by endpoint. A retried negotiation (a fresh
+/// `config_ephemeral_peers_inner` call with a bumped `retry_attempt`) against the *same* relay
+/// seeds its estimator from what that relay's previous attempt actually learned; a different
+/// relay, or a brand-new connection attempt (`retry_attempt == 0`), always starts fresh at
+/// `INITIAL_PSK_EXCHANGE_TIMEOUT` instead of inheriting an unrelated relay's RTT.
+static RTT_ESTIMATOR_SEEDS: Lazy<StdMutex<HashMap<IpAddr, Duration>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// TCP-style (RFC 6298) smoothed RTT estimator for the PSK exchange, shared across the exit and
+/// entry (multihop) negotiations within a single `config_ephemeral_peers_inner` call so that
+/// later exchanges benefit from what earlier ones learned about the path.
+struct RttEstimator {
+    relay: IpAddr,
+    srtt: Duration,
+    rttvar: Duration,
+    /// Current timeout for the next exchange. Starts at the seed value and is recomputed on
+    /// every sample (from `srtt`/`rttvar`) and every timeout (by doubling).
+    rto: Duration,
+}
+
+impl RttEstimator {
+    fn new(relay: IpAddr, retry_attempt: u32) -> Self {
+        let seed = if retry_attempt == 0 {
+            None
+        } else {
+            RTT_ESTIMATOR_SEEDS.lock().unwrap().get(&relay).copied()
+        }
+        .unwrap_or(INITIAL_PSK_EXCHANGE_TIMEOUT);
+
+        Self {
+            relay,
+            srtt: seed,
+            rttvar: seed / 2,
+            rto: seed,
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        self.rto
+    }
+
+    /// Folds a successful negotiation's wall-clock duration `r` into the estimator, following
+    /// the same smoothing as TCP's RTO estimator (RFC 6298):
+    /// `SRTT = 7/8 * SRTT + 1/8 * R`, `RTTVAR = 3/4 * RTTVAR + 1/4 * |SRTT - R|`.
+    fn on_success(&mut self, r: Duration) {
+        let diff = self.srtt.as_secs_f64() - r.as_secs_f64();
+        self.rttvar =
+            Duration::from_secs_f64((self.rttvar.as_secs_f64() * 3.0 / 4.0) + (diff.abs() / 4.0));
+        self.srtt = Duration::from_secs_f64(
+            (self.srtt.as_secs_f64() * 7.0 / 8.0) + (r.as_secs_f64() / 8.0),
+        );
+
+        let candidate = self.srtt + 4 * self.rttvar;
+        self.rto = candidate.clamp(MIN_PSK_EXCHANGE_TIMEOUT, MAX_PSK_EXCHANGE_TIMEOUT);
+        self.publish_rto();
+    }
+
+    /// A timed-out exchange carries no usable RTT sample, so just back off the current RTO
+    /// (Karn's algorithm) rather than folding a fabricated sample into `srtt`/`rttvar`.
+    fn on_timeout(&mut self) {
+        self.rto = (self.rto.saturating_mul(PSK_EXCHANGE_TIMEOUT_MULTIPLIER))
+            .min(MAX_PSK_EXCHANGE_TIMEOUT);
+        self.publish_rto();
+    }
+
+    /// Makes the current RTO available to the next `RttEstimator::new()` for this same relay,
+    /// including one created by a retried negotiation after this estimator (and its
+    /// `config_ephemeral_peers_inner` call) has gone away.
+    fn publish_rto(&self) {
+        RTT_ESTIMATOR_SEEDS
+            .lock()
+            .unwrap()
+            .insert(self.relay, self.rto);
+    }
+}
+
+#[cfg(test)]
+mod rtt_estimator_tests {
+    use super::*;
+
+    fn estimator(seed: Duration) -> RttEstimator {
+        RttEstimator {
+            relay: IpAddr::from([127, 0, 0, 1]),
+            srtt: seed,
+            rttvar: seed / 2,
+            rto: seed,
+        }
+    }
+
+    #[test]
+    fn on_success_lowers_rto_below_initial_seed_on_a_fast_link() {
+        let mut rtt = estimator(INITIAL_PSK_EXCHANGE_TIMEOUT);
+        for _ in 0..30 {
+            rtt.on_success(Duration::from_millis(100));
+        }
+        assert!(
+            rtt.timeout() < INITIAL_PSK_EXCHANGE_TIMEOUT,
+            "fast, consistent RTT samples should pull the RTO well below the pessimistic seed"
+        );
+        assert!(rtt.timeout() >= MIN_PSK_EXCHANGE_TIMEOUT);
+    }
+
+    #[test]
+    fn on_success_never_goes_below_the_minimum() {
+        let mut rtt = estimator(Duration::from_millis(50));
+        rtt.on_success(Duration::from_millis(1));
+        assert!(rtt.timeout() >= MIN_PSK_EXCHANGE_TIMEOUT);
+    }
+
+    #[test]
+    fn on_timeout_doubles_the_rto() {
+        let mut rtt = estimator(Duration::from_secs(1));
+        rtt.on_timeout();
+        assert_eq!(rtt.timeout(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn on_timeout_is_capped_at_the_max() {
+        let mut rtt = estimator(MAX_PSK_EXCHANGE_TIMEOUT);
+        rtt.on_timeout();
+        assert_eq!(rtt.timeout(), MAX_PSK_EXCHANGE_TIMEOUT);
+    }
+}
+
+/// RFC 4821-style Packetization-Layer Path MTU Discovery, run once the tunnel is up so the
+/// effective MTU reflects the path to the relay rather than a hard-coded platform clamp.
+mod path_mtu_discovery {
+    use std::{
+        collections::HashMap,
+        net::{IpAddr, SocketAddr},
+        sync::Mutex,
+        time::Duration,
+    };
+
+    use once_cell::sync::Lazy;
+
+    use super::Config;
+
+    const IPV4_MIN_MTU: u16 = 576;
+    const IPV6_MIN_MTU: u16 = 1280;
+    const WIREGUARD_IPV4_OVERHEAD: u16 = 60;
+    const WIREGUARD_IPV6_OVERHEAD: u16 = 80;
+    const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Discovered MTUs, keyed by the probed relay endpoint, so reconnecting to the same relay
+    /// skips the search entirely.
+    static MTU_CACHE: Lazy<Mutex<HashMap<SocketAddr, u16>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+
+    /// The peer this tunnel talks to directly over the network: the entry relay in multihop,
+    /// the exit relay otherwise. PMTUD probes go here (not at `ipv4_gateway`, which is only the
+    /// in-tunnel next hop) so the probe's IP version and port match what's actually reached.
+    pub(super) fn active_peer_endpoint(config: &Config) -> SocketAddr {
+        if config.is_multihop() {
+            config.entry_peer.endpoint
+        } else {
+            config.exit_peer().endpoint
+        }
+    }
+
+    /// Searches `[floor, config.mtu]` for the largest MTU that a DF-bit probe toward the active
+    /// peer actually gets through at, and returns that size minus the WireGuard framing
+    /// overhead. Returns `None` if not even the floor gets through.
+    pub async fn discover_path_mtu(config: &Config) -> Option<u16> {
+        let target = active_peer_endpoint(config);
+
+        if let Some(cached) = MTU_CACHE.lock().unwrap().get(&target) {
+            log::debug!("Using cached path MTU {cached} for {target}");
+            return Some(*cached);
+        }
+
+        let (floor, overhead) = match target {
+            SocketAddr::V4(_) => (IPV4_MIN_MTU, WIREGUARD_IPV4_OVERHEAD),
+            SocketAddr::V6(_) => (IPV6_MIN_MTU, WIREGUARD_IPV6_OVERHEAD),
+        };
+        let ceiling = config.mtu.max(floor);
+
+        let discovered = binary_search_mtu(target, floor, ceiling).await?;
+        // No further floor clamp here: `discovered` is already known to fit on the wire, so
+        // subtracting the framing overhead gives the largest *inner* MTU that's actually
+        // confirmed to work. Re-clamping up to `floor` (a wire-level probe size) would silently
+        // push `config.mtu` above what was verified whenever `discovered` lands within
+        // `overhead` bytes of `floor` -- exactly the near-minimum-MTU path this search most needs
+        // to get right.
+        let effective_mtu = discovered.saturating_sub(overhead);
+
+        MTU_CACHE.lock().unwrap().insert(target, effective_mtu);
+        Some(effective_mtu)
+    }
+
+    /// Binary-searches `[floor, ceiling]` for the largest probe size that fits, as judged by
+    /// `probe`: an `EMSGSIZE` on send or an ICMP "fragmentation needed"/"packet too big" within
+    /// `PROBE_TIMEOUT` counts as "too big", while a reply or no response at all counts as "fits".
+    /// Returns `None` if the floor itself never gets through.
+    async fn binary_search_mtu(target: SocketAddr, floor: u16, ceiling: u16) -> Option<u16> {
+        binary_search_mtu_with(floor, ceiling, |size| probe(target, size)).await
+    }
+
+    /// The actual search, parameterized over the probe so it can be driven by a fake predicate
+    /// in tests without touching the network.
+    async fn binary_search_mtu_with<F, Fut>(floor: u16, ceiling: u16, mut fits: F) -> Option<u16>
+    where
+        F: FnMut(u16) -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        if !fits(floor).await {
+            return None;
+        }
+        if ceiling > floor && fits(ceiling).await {
+            return Some(ceiling);
+        }
+
+        let (mut lo, mut hi) = (floor, ceiling);
+        while lo + 1 < hi {
+            let mid = lo + (hi - lo) / 2;
+            if fits(mid).await {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Some(lo)
+    }
+
+    /// Sends a single DF-bit probe of `size` zeroed bytes to `target` and waits up to
+    /// `PROBE_TIMEOUT` to find out whether it got through. A real WireGuard relay never replies
+    /// to a payload like this one (it isn't a handshake message), so the absence of a reply is
+    /// the expected outcome either way and can't be used as the "fits" signal. Instead this relies
+    /// on the DF bit actually doing its job: an immediate `EMSGSIZE` from `send_to`, or an
+    /// asynchronous ICMP "fragmentation needed"/"packet too big" surfacing on the socket's error
+    /// queue, both mean "too big"; anything else (an actual reply, or silence for the whole
+    /// timeout) is treated as "fits".
+    async fn probe(target: SocketAddr, size: u16) -> bool {
+        let socket = match tokio::net::UdpSocket::bind(match target {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        })
+        .await
+        {
+            Ok(socket) => socket,
+            Err(error) => {
+                log::warn!("Failed to bind PMTUD probe socket: {error}");
+                return false;
+            }
+        };
+
+        set_dont_fragment(&socket, target.ip());
+        enable_recv_err(&socket, target.ip());
+
+        let payload = vec![0u8; size as usize];
+        match socket.send_to(&payload, target).await {
+            Ok(_) => {}
+            Err(error) if error.raw_os_error() == Some(libc::EMSGSIZE) => return false,
+            Err(_) => return false,
+        }
+
+        match tokio::time::timeout(PROBE_TIMEOUT, wait_for_icmp_too_big(&socket)).await {
+            Ok(()) => false,
+            Err(_timeout) => true,
+        }
+    }
+
+    /// Enables delivery of asynchronous socket errors (notably ICMP "fragmentation
+    /// needed"/"packet too big") onto the socket's error queue, readable via `MSG_ERRQUEUE`.
+    #[cfg(target_os = "linux")]
+    fn enable_recv_err(socket: &tokio::net::UdpSocket, target_ip: IpAddr) {
+        use std::os::fd::AsRawFd;
+        let fd = socket.as_raw_fd();
+        let (level, name) = match target_ip {
+            IpAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_RECVERR),
+            IpAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_RECVERR),
+        };
+        let value: libc::c_int = 1;
+        unsafe {
+            let _ = libc::setsockopt(
+                fd,
+                level,
+                name,
+                &value as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&value) as libc::socklen_t,
+            );
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn enable_recv_err(_socket: &tokio::net::UdpSocket, _target_ip: IpAddr) {
+        // IP_RECVERR/MSG_ERRQUEUE are Linux-specific; other platforms fall back to whatever
+        // `send_to`'s immediate EMSGSIZE check above catches.
+    }
+
+    /// Waits for an ICMP "fragmentation needed"/"packet too big" notification to surface on
+    /// `socket`'s error queue. Never resolves on platforms without error-queue support, so the
+    /// caller's timeout is what governs there.
+    #[cfg(target_os = "linux")]
+    async fn wait_for_icmp_too_big(socket: &tokio::net::UdpSocket) {
+        use std::os::fd::AsRawFd;
+        let fd = socket.as_raw_fd();
+        loop {
+            let Ok(guard) = socket.readable().await else {
+                return;
+            };
+            let mut buf = [0u8; 512];
+            let received = unsafe {
+                libc::recv(
+                    fd,
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_ERRQUEUE,
+                )
+            };
+            guard.clear_ready();
+            if received >= 0 {
+                return;
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn wait_for_icmp_too_big(_socket: &tokio::net::UdpSocket) {
+        std::future::pending().await
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_dont_fragment(socket: &tokio::net::UdpSocket, gateway: IpAddr) {
+        use std::os::fd::AsRawFd;
+        let fd = socket.as_raw_fd();
+        let (level, name, value) = match gateway {
+            IpAddr::V4(_) => (
+                libc::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                libc::IP_PMTUDISC_DO,
+            ),
+            IpAddr::V6(_) => (
+                libc::IPPROTO_IPV6,
+                libc::IPV6_MTU_DISCOVER,
+                libc::IP_PMTUDISC_DO,
+            ),
+        };
+        unsafe {
+            let _ = libc::setsockopt(
+                fd,
+                level,
+                name,
+                &value as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&value) as libc::socklen_t,
+            );
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_dont_fragment(_socket: &tokio::net::UdpSocket, _gateway: IpAddr) {
+        // Other platforms set the DF bit on a per-packet basis elsewhere, or the probe relies on
+        // the OS default of fragmenting rather than dropping; accuracy is best-effort there.
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn converges_on_largest_size_that_fits() {
+            let result = binary_search_mtu_with(100, 2000, |size| async move { size <= 1350 }).await;
+            assert_eq!(result, Some(1350));
+        }
+
+        #[tokio::test]
+        async fn returns_none_when_floor_does_not_fit() {
+            let result = binary_search_mtu_with(100, 2000, |_size| async { false }).await;
+            assert_eq!(result, None);
+        }
+
+        #[tokio::test]
+        async fn returns_ceiling_when_everything_fits() {
+            let result = binary_search_mtu_with(100, 2000, |_size| async { true }).await;
+            assert_eq!(result, Some(2000));
+        }
+    }
+}
+
+/// Frames WireGuard datagrams as binary WebSocket messages carried over a TLS session to a
+/// relay's 443 endpoint, so that blocked-UDP networks see nothing but ordinary HTTPS.
+mod websocket_obfuscation {
+    use std::{io, net::SocketAddr};
+
+    use tokio::{net::UdpSocket, task::JoinHandle};
+    use tokio_rustls::{client::TlsStream, TlsConnector};
+    use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+    use super::super::obfuscation::ObfuscatorHandle;
+
+    /// Where the WebSocket-over-TLS relay should connect to. The local WG-facing endpoint it
+    /// exposes in its place is picked by `WebSocketTlsObfuscator::spawn`.
+    #[derive(Debug, Clone)]
+    pub struct WebSocketTlsConfig {
+        /// Address of the relay's TLS listener, normally `<gateway>:443`.
+        pub remote_addr: SocketAddr,
+        /// Hostname presented in the TLS ClientHello / used for certificate verification.
+        pub sni_hostname: String,
+        /// Routing mark to apply to the underlying TCP socket, so the TLS session bypasses the
+        /// tunnel it's helping to negotiate instead of looping back into it.
+        pub fwmark: Option<u32>,
+    }
+
+    /// A running WebSocket-over-TLS relay. Binds a local UDP socket that the WireGuard tunnel
+    /// (and, transitively, the ephemeral peer negotiation) talks to as if it were the real
+    /// relay; everything written there is framed and shipped over the WebSocket/TLS session.
+    pub struct WebSocketTlsObfuscator {
+        local_addr: SocketAddr,
+        task: JoinHandle<()>,
+    }
+
+    impl WebSocketTlsObfuscator {
+        pub async fn spawn(config: WebSocketTlsConfig) -> io::Result<Self> {
+            let local_socket = UdpSocket::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+            let local_addr = local_socket.local_addr()?;
+
+            let tls_stream = connect_tls(&config).await?;
+            let (ws_stream, _response) = tokio_tungstenite::client_async_tls(
+                format!("wss://{}/", config.sni_hostname),
+                tls_stream,
+            )
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+            let task = tokio::spawn(run_relay(local_socket, ws_stream));
+
+            Ok(Self { local_addr, task })
+        }
+
+        /// The local UDP endpoint WireGuard should be pointed at instead of the real relay.
+        pub fn local_addr(&self) -> SocketAddr {
+            self.local_addr
+        }
+
+        /// Wrap this relay in the same handle type the rest of `reconfigure_tunnel` already
+        /// knows how to abort and swap out.
+        pub fn into_obfuscator_handle(self) -> ObfuscatorHandle {
+            ObfuscatorHandle::new(self.task, self.local_addr)
+        }
+    }
+
+    async fn connect_tls(
+        config: &WebSocketTlsConfig,
+    ) -> io::Result<TlsStream<tokio::net::TcpStream>> {
+        let tcp_stream = tokio::net::TcpStream::connect(config.remote_addr).await?;
+        if let Some(fwmark) = config.fwmark {
+            set_fwmark(&tcp_stream, fwmark);
+        }
+        let connector = TlsConnector::from(talpid_tls::client_config());
+        let server_name = rustls_pki_types::ServerName::try_from(config.sni_hostname.clone())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        connector.connect(server_name, tcp_stream).await
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_fwmark(stream: &tokio::net::TcpStream, fwmark: u32) {
+        use std::os::fd::AsRawFd;
+        let fd = stream.as_raw_fd();
+        unsafe {
+            let _ = libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_MARK,
+                &fwmark as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&fwmark) as libc::socklen_t,
+            );
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_fwmark(_stream: &tokio::net::TcpStream, _fwmark: u32) {}
+
+    /// Ferries datagrams between the local UDP socket and the WebSocket/TLS session, framing
+    /// each WG packet as one binary WebSocket message and reassembling frames that arrive split
+    /// across multiple TLS reads.
+    async fn run_relay(
+        local_socket: UdpSocket,
+        mut ws_stream: WebSocketStream<TlsStream<tokio::net::TcpStream>>,
+    ) {
+        use futures::{SinkExt, StreamExt};
+
+        let mut udp_buf = vec![0u8; u16::MAX as usize];
+        let mut wg_peer_addr = None;
+
+        loop {
+            tokio::select! {
+                result = local_socket.recv_from(&mut udp_buf) => {
+                    let Ok((len, addr)) = result else { break };
+                    wg_peer_addr = Some(addr);
+                    if ws_stream.send(Message::Binary(udp_buf[..len].to_vec())).await.is_err() {
+                        break;
+                    }
+                }
+                frame = ws_stream.next() => {
+                    match frame {
+                        Some(Ok(Message::Binary(datagram))) => {
+                            // Partial TLS reads are already reassembled into whole WebSocket
+                            // frames by `WebSocketStream`; each `Message::Binary` here is exactly
+                            // one WG datagram.
+                            if let Some(addr) = wg_peer_addr {
+                                let _ = local_socket.send_to(&datagram, addr).await;
+                            }
+                        }
+                        // A long-lived session over port 443 routinely sees Ping keepalives from
+                        // the server or an intermediate proxy; answer them instead of tearing the
+                        // relay (and the tunnel) down.
+                        Some(Ok(Message::Ping(payload))) => {
+                            if ws_stream.send(Message::Pong(payload)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) => break,
+                        // Pong replies and anything else WireGuard never sends carry no datagram
+                        // to forward; ignore rather than treating them as fatal.
+                        Some(Ok(_)) => {}
+                        Some(Err(_)) | None => break,
+                    }
+                }
+            }
+        }
+    }
+}